@@ -0,0 +1,274 @@
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::env;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::Arc;
+use std::thread;
+
+use toy_pay::client::ClientBalance;
+use toy_pay::token::Token;
+use toy_pay::{Engine, Error};
+
+/// One transaction or balance query, sent as a single line of JSON.
+#[derive(Debug, Deserialize)]
+#[serde(tag = "op", rename_all = "lowercase")]
+enum Request {
+    Deposit { client: u16, tx: u32, amount: Decimal },
+    Withdrawal { client: u16, tx: u32, amount: Decimal },
+    Dispute { client: u16, tx: u32 },
+    Resolve { client: u16, tx: u32 },
+    Chargeback { client: u16, tx: u32 },
+    Query { client: u16 },
+}
+
+impl From<Request> for Token {
+    fn from(request: Request) -> Self {
+        match request {
+            Request::Deposit { client, tx, amount } => Token::Deposit {
+                client_id: client,
+                transaction_id: tx,
+                amount,
+            },
+            Request::Withdrawal { client, tx, amount } => Token::Withdrawal {
+                client_id: client,
+                transaction_id: tx,
+                amount,
+            },
+            Request::Dispute { client, tx } => Token::Dispute {
+                client_id: client,
+                transaction_id: tx,
+            },
+            Request::Resolve { client, tx } => Token::Resolve {
+                client_id: client,
+                transaction_id: tx,
+            },
+            Request::Chargeback { client, tx } => Token::Chargeback {
+                client_id: client,
+                transaction_id: tx,
+            },
+            Request::Query { .. } => unreachable!("queries are handled separately from tokens"),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum ErrorKind {
+    LockedAccount,
+    TransactionDoesNotExist,
+    NotEnoughCredit,
+    InvalidDisputeState,
+    NegativeHeldAmount,
+    NegativeAmount,
+    BadRequest,
+    UnknownClient,
+}
+
+impl From<&Error> for ErrorKind {
+    fn from(error: &Error) -> Self {
+        match error {
+            Error::LockedAccount(_) => ErrorKind::LockedAccount,
+            Error::TransactionDoesNotExist(_, _) => ErrorKind::TransactionDoesNotExist,
+            Error::NotEnoughCredit(_) => ErrorKind::NotEnoughCredit,
+            Error::InvalidDisputeState(_, _) => ErrorKind::InvalidDisputeState,
+            Error::NegativeHeldAmount(_) => ErrorKind::NegativeHeldAmount,
+            Error::NegativeAmount(_) => ErrorKind::NegativeAmount,
+        }
+    }
+}
+
+/// The reply to a single `Request`, sent back as a single line of JSON.
+#[derive(Debug, Serialize)]
+#[serde(tag = "status", rename_all = "lowercase")]
+enum Response {
+    Ok { balance: Option<ClientBalance> },
+    Error { kind: ErrorKind, message: String },
+}
+
+impl From<Result<(), Error>> for Response {
+    fn from(result: Result<(), Error>) -> Self {
+        match result {
+            Ok(()) => Response::Ok { balance: None },
+            Err(ref error) => Response::Error {
+                kind: ErrorKind::from(error),
+                message: error.to_string(),
+            },
+        }
+    }
+}
+
+fn handle_request(engine: &Engine, request: Request) -> Response {
+    match request {
+        Request::Query { client } => match engine.balance(client) {
+            Some(balance) => Response::Ok {
+                balance: Some(balance),
+            },
+            None => Response::Error {
+                kind: ErrorKind::UnknownClient,
+                message: format!("The client number: {client} has no recorded transactions."),
+            },
+        },
+        request => Response::from(engine.apply(request.into())),
+    }
+}
+
+fn handle_connection(stream: TcpStream, engine: &Engine) {
+    let Ok(mut writer) = stream.try_clone() else {
+        return;
+    };
+    let reader = BufReader::new(stream);
+
+    for line in reader.lines() {
+        let Ok(line) = line else { break };
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let response = match serde_json::from_str::<Request>(&line) {
+            Ok(request) => handle_request(engine, request),
+            Err(err) => Response::Error {
+                kind: ErrorKind::BadRequest,
+                message: err.to_string(),
+            },
+        };
+
+        let Ok(body) = serde_json::to_string(&response) else {
+            break;
+        };
+        if writeln!(writer, "{body}").is_err() {
+            break;
+        }
+    }
+}
+
+fn main() {
+    let addr = env::args().nth(1).unwrap_or_else(|| "127.0.0.1:7878".to_string());
+    let listener = TcpListener::bind(&addr).expect("failed to bind address");
+    let engine = Arc::new(Engine::new());
+
+    for stream in listener.incoming() {
+        let Ok(stream) = stream else { continue };
+        let engine = Arc::clone(&engine);
+        thread::spawn(move || handle_connection(stream, &engine));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_error_kind_mapping_covers_every_variant() {
+        assert!(matches!(
+            ErrorKind::from(&Error::LockedAccount(1)),
+            ErrorKind::LockedAccount
+        ));
+        assert!(matches!(
+            ErrorKind::from(&Error::TransactionDoesNotExist(1, 2)),
+            ErrorKind::TransactionDoesNotExist
+        ));
+        assert!(matches!(
+            ErrorKind::from(&Error::NotEnoughCredit(1)),
+            ErrorKind::NotEnoughCredit
+        ));
+        assert!(matches!(
+            ErrorKind::from(&Error::InvalidDisputeState(1, 2)),
+            ErrorKind::InvalidDisputeState
+        ));
+        assert!(matches!(
+            ErrorKind::from(&Error::NegativeHeldAmount(1)),
+            ErrorKind::NegativeHeldAmount
+        ));
+        assert!(matches!(
+            ErrorKind::from(&Error::NegativeAmount(1)),
+            ErrorKind::NegativeAmount
+        ));
+    }
+
+    #[test]
+    fn test_handle_request_deposit_dispute_query_sequence() {
+        let engine = Engine::new();
+
+        let response = handle_request(
+            &engine,
+            Request::Deposit {
+                client: 1,
+                tx: 1,
+                amount: Decimal::new(50, 1),
+            },
+        );
+        assert!(matches!(response, Response::Ok { balance: None }));
+
+        let response = handle_request(&engine, Request::Query { client: 1 });
+        match response {
+            Response::Ok { balance: Some(b) } => {
+                assert_eq!(b.client_id, 1);
+                assert_eq!(b.available, Decimal::new(50000, 4));
+            }
+            other => panic!("expected a balance, got {other:?}"),
+        }
+
+        let response = handle_request(&engine, Request::Dispute { client: 1, tx: 1 });
+        assert!(matches!(response, Response::Ok { balance: None }));
+
+        let response = handle_request(&engine, Request::Resolve { client: 1, tx: 1 });
+        assert!(matches!(response, Response::Ok { balance: None }));
+
+        let response = handle_request(
+            &engine,
+            Request::Deposit {
+                client: 1,
+                tx: 2,
+                amount: Decimal::ONE,
+            },
+        );
+        assert!(matches!(response, Response::Ok { balance: None }));
+
+        let response = handle_request(&engine, Request::Dispute { client: 1, tx: 2 });
+        assert!(matches!(response, Response::Ok { balance: None }));
+
+        let response = handle_request(&engine, Request::Chargeback { client: 1, tx: 2 });
+        assert!(matches!(response, Response::Ok { balance: None }));
+
+        let response = handle_request(
+            &engine,
+            Request::Deposit {
+                client: 1,
+                tx: 3,
+                amount: Decimal::ONE,
+            },
+        );
+        match response {
+            Response::Error { kind, .. } => assert!(matches!(kind, ErrorKind::LockedAccount)),
+            other => panic!("expected the locked account to reject further deposits, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_handle_request_query_unknown_client() {
+        let engine = Engine::new();
+        let response = handle_request(&engine, Request::Query { client: 1 });
+        match response {
+            Response::Error { kind, .. } => assert!(matches!(kind, ErrorKind::UnknownClient)),
+            other => panic!("expected an unknown-client error, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_handle_request_negative_deposit_is_rejected() {
+        let engine = Engine::new();
+        let response = handle_request(
+            &engine,
+            Request::Deposit {
+                client: 1,
+                tx: 1,
+                amount: Decimal::new(-1000, 0),
+            },
+        );
+        match response {
+            Response::Error { kind, .. } => assert!(matches!(kind, ErrorKind::NegativeAmount)),
+            other => panic!("expected a negative-amount error, got {other:?}"),
+        }
+    }
+}
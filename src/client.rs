@@ -1,8 +1,10 @@
 use crate::Error;
 use crate::Transaction;
+use crate::TxKind;
+use crate::TxState;
 use rust_decimal::prelude::*;
 use rust_decimal_macros::dec;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use std::collections::BTreeMap;
 
 #[derive(Debug, Deserialize, Clone)]
@@ -15,6 +17,26 @@ pub struct Client {
     transactions: BTreeMap<u32, Transaction>,
 }
 
+/// A serializable snapshot of a `Client`'s balances, rounded to four decimal
+/// places, suitable for writing out as a CSV row.
+#[derive(Debug, Serialize, Clone, Copy, PartialEq)]
+pub struct ClientBalance {
+    #[serde(rename = "client")]
+    pub client_id: u16,
+    pub available: Decimal,
+    pub held: Decimal,
+    pub total: Decimal,
+    pub locked: bool,
+}
+
+/// Rounds `amount` to four decimal places and rescales it so it always
+/// carries exactly four, regardless of the scale it arrived with.
+fn round_to_four_places(amount: Decimal) -> Decimal {
+    let mut rounded = amount.round_dp(4);
+    rounded.rescale(4);
+    rounded
+}
+
 impl Client {
     pub fn new(id: u16) -> Self {
         Client {
@@ -27,6 +49,18 @@ impl Client {
         }
     }
 
+    /// Returns a rounded, serializable snapshot of this client's balances,
+    /// each rescaled to display with exactly four decimal places.
+    pub fn balance(&self) -> ClientBalance {
+        ClientBalance {
+            client_id: self.client_id,
+            available: round_to_four_places(self.available_amount),
+            held: round_to_four_places(self.held_amount),
+            total: round_to_four_places(self.total_amount),
+            locked: self.locked,
+        }
+    }
+
     pub fn deposit(
         &mut self,
         transaction_id: u32,
@@ -35,8 +69,13 @@ impl Client {
         if self.locked {
             return Err(Error::LockedAccount(self.client_id));
         }
-        self.transactions
-            .insert(transaction_id, Transaction::new(transaction_id, amount));
+        if amount < Decimal::ZERO {
+            return Err(Error::NegativeAmount(self.client_id));
+        }
+        self.transactions.insert(
+            transaction_id,
+            Transaction::new(transaction_id, amount, TxKind::Deposit),
+        );
         self.total_amount += amount;
         self.available_amount += amount;
         Ok(())
@@ -50,25 +89,44 @@ impl Client {
         if self.locked {
             return Err(Error::LockedAccount(self.client_id));
         }
+        if amount < Decimal::ZERO {
+            return Err(Error::NegativeAmount(self.client_id));
+        }
 
         if self.available_amount < amount {
             return Err(Error::NotEnoughCredit(self.client_id));
         }
-        self.transactions
-            .insert(transaction_id, Transaction::new(transaction_id, amount));
+        self.transactions.insert(
+            transaction_id,
+            Transaction::new(transaction_id, amount, TxKind::Withdrawal),
+        );
         self.total_amount -= amount;
         self.available_amount -= amount;
         Ok(())
     }
 
+    /// Puts `amount` on hold, pending a dispute. A deposit's funds are frozen
+    /// out of `available`; a withdrawal's funds are reinstated into `total`
+    /// while the dispute is pending, but not yet released into `available`.
     pub fn dispute(&mut self, transaction_id: u32) -> std::result::Result<(), Error> {
         if self.locked {
             return Err(Error::LockedAccount(self.client_id));
         }
         if let Some(transaction) = self.transactions.get_mut(&transaction_id) {
-            transaction.set_dispute_status(true);
-            self.held_amount += transaction.get_amount();
-            self.available_amount -= transaction.get_amount();
+            if transaction.get_state() != TxState::Processed {
+                return Err(Error::InvalidDisputeState(transaction_id, self.client_id));
+            }
+            let amount = transaction.get_amount();
+            let held_amount = self.held_amount + amount;
+            if held_amount < dec!(0.0000) {
+                return Err(Error::NegativeHeldAmount(self.client_id));
+            }
+            transaction.set_state(TxState::Disputed);
+            self.held_amount = held_amount;
+            match transaction.get_kind() {
+                TxKind::Deposit => self.available_amount -= amount,
+                TxKind::Withdrawal => self.total_amount += amount,
+            }
         } else {
             return Err(Error::TransactionDoesNotExist(
                 self.client_id,
@@ -78,20 +136,27 @@ impl Client {
         Ok(())
     }
 
+    /// Releases a hold, upholding the original transaction. A deposit's funds
+    /// are returned to `available`; a withdrawal's funds go back out of
+    /// `total`, since the withdrawal stands.
     pub fn resolve(&mut self, transaction_id: u32) -> std::result::Result<(), Error> {
         if self.locked {
             return Err(Error::LockedAccount(self.client_id));
         }
         if let Some(transaction) = self.transactions.get_mut(&transaction_id) {
-            if transaction.get_dispute_status() {
-                transaction.set_dispute_status(false);
-                self.held_amount -= transaction.get_amount();
-                self.available_amount += transaction.get_amount();
-            } else {
-                return Err(Error::TransactionNotDisputed(
-                    transaction_id,
-                    self.client_id,
-                ));
+            if transaction.get_state() != TxState::Disputed {
+                return Err(Error::InvalidDisputeState(transaction_id, self.client_id));
+            }
+            let amount = transaction.get_amount();
+            let held_amount = self.held_amount - amount;
+            if held_amount < dec!(0.0000) {
+                return Err(Error::NegativeHeldAmount(self.client_id));
+            }
+            transaction.set_state(TxState::Resolved);
+            self.held_amount = held_amount;
+            match transaction.get_kind() {
+                TxKind::Deposit => self.available_amount += amount,
+                TxKind::Withdrawal => self.total_amount -= amount,
             }
         } else {
             return Err(Error::TransactionDoesNotExist(
@@ -102,22 +167,29 @@ impl Client {
         Ok(())
     }
 
+    /// Releases a hold, reversing the original transaction and locking the
+    /// account. A deposit is unwound entirely out of `total`; a withdrawal's
+    /// funds are finally released into `available`, since it was reversed.
     pub fn chargeback(&mut self, transaction_id: u32) -> std::result::Result<(), Error> {
         if self.locked {
             return Err(Error::LockedAccount(self.client_id));
         }
         if let Some(transaction) = self.transactions.get_mut(&transaction_id) {
-            if transaction.get_dispute_status() {
-                transaction.set_dispute_status(false);
-                self.held_amount -= transaction.get_amount();
-                self.total_amount -= transaction.get_amount();
-                self.locked = true;
-            } else {
-                return Err(Error::TransactionNotDisputed(
-                    transaction_id,
-                    self.client_id,
-                ));
+            if transaction.get_state() != TxState::Disputed {
+                return Err(Error::InvalidDisputeState(transaction_id, self.client_id));
+            }
+            let amount = transaction.get_amount();
+            let held_amount = self.held_amount - amount;
+            if held_amount < dec!(0.0000) {
+                return Err(Error::NegativeHeldAmount(self.client_id));
+            }
+            transaction.set_state(TxState::ChargedBack);
+            self.held_amount = held_amount;
+            match transaction.get_kind() {
+                TxKind::Deposit => self.total_amount -= amount,
+                TxKind::Withdrawal => self.available_amount += amount,
             }
+            self.locked = true;
         } else {
             return Err(Error::TransactionDoesNotExist(
                 self.client_id,
@@ -129,11 +201,10 @@ impl Client {
 }
 
 #[cfg(test)]
-
 mod tests {
     use crate::client::Client;
     use crate::Error;
-    use rust_decimal::prelude::*;
+    use crate::TxState;
     use rust_decimal_macros::dec;
 
     #[test]
@@ -159,6 +230,17 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_deposit_negative_amount() {
+        let mut client = Client::new(1);
+        assert_eq!(
+            client.deposit(1, dec!(-2.0000)).unwrap_err(),
+            Error::NegativeAmount(1)
+        );
+        assert_eq!(client.total_amount, dec!(0.0000));
+        assert_eq!(client.available_amount, dec!(0.0000));
+    }
+
     #[test]
     fn test_withdrawal_ok_case() {
         let mut client = Client::new(1);
@@ -196,14 +278,33 @@ mod tests {
         assert_eq!(client.available_amount, dec!(3.0000));
     }
 
+    #[test]
+    fn test_withdrawal_negative_amount() {
+        let mut client = Client::new(1);
+        client.deposit(1, dec!(3.0000)).unwrap();
+        assert_eq!(
+            client.withdrawal(2, dec!(-1.0000)).unwrap_err(),
+            Error::NegativeAmount(1)
+        );
+        assert_eq!(client.total_amount, dec!(3.0000));
+        assert_eq!(client.held_amount, dec!(0.0000));
+        assert_eq!(client.available_amount, dec!(3.0000));
+    }
+
     #[test]
     fn test_dispute_ok_case() {
         let mut client = Client::new(1);
         client.deposit(1, dec!(3.0000)).unwrap();
         client.dispute(1).unwrap();
-        assert!(client.transactions.get(&1).unwrap().get_dispute_status());
+        assert_eq!(
+            client.transactions.get(&1).unwrap().get_state(),
+            TxState::Disputed
+        );
         client.deposit(2, dec!(3.0000)).unwrap();
-        assert!(!client.transactions.get(&2).unwrap().get_dispute_status());
+        assert_eq!(
+            client.transactions.get(&2).unwrap().get_state(),
+            TxState::Processed
+        );
         assert_eq!(client.total_amount, dec!(6.0000));
         assert_eq!(client.held_amount, dec!(3.0000));
         assert_eq!(client.available_amount, dec!(3.0000))
@@ -257,7 +358,10 @@ mod tests {
         let mut client = Client::new(1);
         client.deposit(1, dec!(3.0000)).unwrap();
         client.dispute(1).unwrap();
-        assert!(client.transactions.get(&1).unwrap().get_dispute_status());
+        assert_eq!(
+            client.transactions.get(&1).unwrap().get_state(),
+            TxState::Disputed
+        );
         client.deposit(2, dec!(3.0000)).unwrap();
         client.resolve(1).unwrap();
         assert_eq!(client.total_amount, dec!(6.0000));
@@ -270,7 +374,10 @@ mod tests {
         let mut client = Client::new(1);
         client.deposit(1, dec!(3.0000)).unwrap();
         client.dispute(1).unwrap();
-        assert!(client.transactions.get(&1).unwrap().get_dispute_status());
+        assert_eq!(
+            client.transactions.get(&1).unwrap().get_state(),
+            TxState::Disputed
+        );
         client.deposit(2, dec!(3.0000)).unwrap();
         client.locked = true;
         assert_eq!(client.resolve(1).unwrap_err(), Error::LockedAccount(1));
@@ -284,7 +391,10 @@ mod tests {
         let mut client = Client::new(1);
         client.deposit(1, dec!(3.0000)).unwrap();
         client.dispute(1).unwrap();
-        assert!(client.transactions.get(&1).unwrap().get_dispute_status());
+        assert_eq!(
+            client.transactions.get(&1).unwrap().get_state(),
+            TxState::Disputed
+        );
         client.deposit(2, dec!(3.0000)).unwrap();
         assert_eq!(
             client.resolve(3).unwrap_err(),
@@ -300,11 +410,14 @@ mod tests {
         let mut client = Client::new(1);
         client.deposit(1, dec!(3.0000)).unwrap();
         client.dispute(1).unwrap();
-        assert!(client.transactions.get(&1).unwrap().get_dispute_status());
+        assert_eq!(
+            client.transactions.get(&1).unwrap().get_state(),
+            TxState::Disputed
+        );
         client.deposit(2, dec!(3.0000)).unwrap();
         assert_eq!(
             client.resolve(2).unwrap_err(),
-            Error::TransactionNotDisputed(2, 1)
+            Error::InvalidDisputeState(2, 1)
         );
         assert_eq!(client.total_amount, dec!(6.0000));
         assert_eq!(client.held_amount, dec!(3.0000));
@@ -316,7 +429,10 @@ mod tests {
         let mut client = Client::new(1);
         client.deposit(1, dec!(3.0000)).unwrap();
         client.dispute(1).unwrap();
-        assert!(client.transactions.get(&1).unwrap().get_dispute_status());
+        assert_eq!(
+            client.transactions.get(&1).unwrap().get_state(),
+            TxState::Disputed
+        );
         client.deposit(2, dec!(3.0000)).unwrap();
         client.chargeback(1).unwrap();
         assert!(client.locked);
@@ -330,7 +446,10 @@ mod tests {
         let mut client = Client::new(1);
         client.deposit(1, dec!(3.0000)).unwrap();
         client.dispute(1).unwrap();
-        assert!(client.transactions.get(&1).unwrap().get_dispute_status());
+        assert_eq!(
+            client.transactions.get(&1).unwrap().get_state(),
+            TxState::Disputed
+        );
         client.deposit(2, dec!(3.0000)).unwrap();
         client.dispute(2).unwrap();
         client.chargeback(1).unwrap();
@@ -346,7 +465,10 @@ mod tests {
         let mut client = Client::new(1);
         client.deposit(1, dec!(3.0000)).unwrap();
         client.dispute(1).unwrap();
-        assert!(client.transactions.get(&1).unwrap().get_dispute_status());
+        assert_eq!(
+            client.transactions.get(&1).unwrap().get_state(),
+            TxState::Disputed
+        );
         client.deposit(2, dec!(3.0000)).unwrap();
         assert_eq!(
             client.chargeback(3).unwrap_err(),
@@ -363,15 +485,58 @@ mod tests {
         let mut client = Client::new(1);
         client.deposit(1, dec!(3.0000)).unwrap();
         client.dispute(1).unwrap();
-        assert!(client.transactions.get(&1).unwrap().get_dispute_status());
+        assert_eq!(
+            client.transactions.get(&1).unwrap().get_state(),
+            TxState::Disputed
+        );
         client.deposit(2, dec!(3.0000)).unwrap();
         assert_eq!(
             client.chargeback(2).unwrap_err(),
-            Error::TransactionNotDisputed(2, 1)
+            Error::InvalidDisputeState(2, 1)
         );
         assert!(!client.locked);
         assert_eq!(client.total_amount, dec!(6.0000));
         assert_eq!(client.held_amount, dec!(3.0000));
         assert_eq!(client.available_amount, dec!(3.0000))
     }
+
+    #[test]
+    fn test_dispute_withdrawal_reinstates_total_not_available() {
+        let mut client = Client::new(1);
+        client.deposit(1, dec!(5.0000)).unwrap();
+        client.withdrawal(2, dec!(3.0000)).unwrap();
+        client.dispute(2).unwrap();
+        assert_eq!(
+            client.transactions.get(&2).unwrap().get_state(),
+            TxState::Disputed
+        );
+        assert_eq!(client.total_amount, dec!(5.0000));
+        assert_eq!(client.held_amount, dec!(3.0000));
+        assert_eq!(client.available_amount, dec!(2.0000))
+    }
+
+    #[test]
+    fn test_resolve_withdrawal_undoes_the_reinstatement() {
+        let mut client = Client::new(1);
+        client.deposit(1, dec!(5.0000)).unwrap();
+        client.withdrawal(2, dec!(3.0000)).unwrap();
+        client.dispute(2).unwrap();
+        client.resolve(2).unwrap();
+        assert_eq!(client.total_amount, dec!(2.0000));
+        assert_eq!(client.held_amount, dec!(0.0000));
+        assert_eq!(client.available_amount, dec!(2.0000))
+    }
+
+    #[test]
+    fn test_chargeback_withdrawal_releases_funds_to_available() {
+        let mut client = Client::new(1);
+        client.deposit(1, dec!(5.0000)).unwrap();
+        client.withdrawal(2, dec!(3.0000)).unwrap();
+        client.dispute(2).unwrap();
+        client.chargeback(2).unwrap();
+        assert!(client.locked);
+        assert_eq!(client.total_amount, dec!(5.0000));
+        assert_eq!(client.held_amount, dec!(0.0000));
+        assert_eq!(client.available_amount, dec!(5.0000))
+    }
 }
@@ -0,0 +1,159 @@
+use crate::client::{Client, ClientBalance};
+use crate::token::Token;
+use crate::Error;
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+
+/// Applies a single token to `client`, surfacing whatever the underlying
+/// operation returns. Shared by the batch CLI, which applies tokens directly
+/// to an unlocked `BTreeMap`, and `Engine`, which applies them through a lock.
+pub fn apply_token(client: &mut Client, token: Token) -> Result<(), Error> {
+    match token {
+        Token::Deposit {
+            transaction_id,
+            amount,
+            ..
+        } => client.deposit(transaction_id, amount),
+        Token::Withdrawal {
+            transaction_id,
+            amount,
+            ..
+        } => client.withdrawal(transaction_id, amount),
+        Token::Dispute { transaction_id, .. } => client.dispute(transaction_id),
+        Token::Resolve { transaction_id, .. } => client.resolve(transaction_id),
+        Token::Chargeback { transaction_id, .. } => client.chargeback(transaction_id),
+    }
+}
+
+/// A thread-safe, long-lived ledger of client balances.
+///
+/// Wraps the same `BTreeMap<u16, Client>` the batch CLI builds in one shot,
+/// behind a lock, so a server can apply transactions one at a time as they
+/// arrive over the network instead of processing a whole file up front.
+#[derive(Default)]
+pub struct Engine {
+    clients: Mutex<BTreeMap<u16, Client>>,
+}
+
+impl Engine {
+    pub fn new() -> Self {
+        Engine::default()
+    }
+
+    /// Applies a single transaction to the engine's shared state.
+    pub fn apply(&self, token: Token) -> Result<(), Error> {
+        let client_id = token.client_id();
+        let mut clients = self.clients.lock().unwrap();
+        let client = clients
+            .entry(client_id)
+            .or_insert_with(|| Client::new(client_id));
+        apply_token(client, token)
+    }
+
+    /// Returns the current balance for a client, if any transaction has been
+    /// applied for them. A client is recorded on its first transaction even
+    /// when that transaction is rejected.
+    pub fn balance(&self, client_id: u16) -> Option<ClientBalance> {
+        let clients = self.clients.lock().unwrap();
+        clients.get(&client_id).map(Client::balance)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn test_balance_is_none_for_unknown_client() {
+        let engine = Engine::new();
+        assert_eq!(engine.balance(1), None);
+    }
+
+    #[test]
+    fn test_deposit_dispute_resolve_sequence() {
+        let engine = Engine::new();
+        engine
+            .apply(Token::Deposit {
+                client_id: 1,
+                transaction_id: 1,
+                amount: dec!(5.0),
+            })
+            .unwrap();
+        engine
+            .apply(Token::Dispute {
+                client_id: 1,
+                transaction_id: 1,
+            })
+            .unwrap();
+
+        let balance = engine.balance(1).unwrap();
+        assert_eq!(balance.available, dec!(0.0000));
+        assert_eq!(balance.held, dec!(5.0000));
+        assert_eq!(balance.total, dec!(5.0000));
+
+        engine
+            .apply(Token::Resolve {
+                client_id: 1,
+                transaction_id: 1,
+            })
+            .unwrap();
+
+        let balance = engine.balance(1).unwrap();
+        assert_eq!(balance.available, dec!(5.0000));
+        assert_eq!(balance.held, dec!(0.0000));
+    }
+
+    #[test]
+    fn test_deposit_dispute_chargeback_locks_account() {
+        let engine = Engine::new();
+        engine
+            .apply(Token::Deposit {
+                client_id: 1,
+                transaction_id: 1,
+                amount: dec!(5.0),
+            })
+            .unwrap();
+        engine
+            .apply(Token::Dispute {
+                client_id: 1,
+                transaction_id: 1,
+            })
+            .unwrap();
+        engine
+            .apply(Token::Chargeback {
+                client_id: 1,
+                transaction_id: 1,
+            })
+            .unwrap();
+
+        let balance = engine.balance(1).unwrap();
+        assert_eq!(balance.available, dec!(0.0000));
+        assert_eq!(balance.held, dec!(0.0000));
+        assert_eq!(balance.total, dec!(0.0000));
+        assert!(balance.locked);
+
+        assert_eq!(
+            engine.apply(Token::Deposit {
+                client_id: 1,
+                transaction_id: 2,
+                amount: dec!(1.0),
+            }),
+            Err(Error::LockedAccount(1))
+        );
+    }
+
+    #[test]
+    fn test_negative_deposit_is_rejected() {
+        let engine = Engine::new();
+        assert_eq!(
+            engine.apply(Token::Deposit {
+                client_id: 1,
+                transaction_id: 1,
+                amount: dec!(-1000.0),
+            }),
+            Err(Error::NegativeAmount(1))
+        );
+        assert_eq!(engine.balance(1), Some(Client::new(1).balance()));
+    }
+}
@@ -0,0 +1,18 @@
+use thiserror::Error;
+
+#[derive(Debug, PartialEq, Error)]
+pub enum Error {
+    /// There client that requested an operation has the account locked.
+    #[error("The client number: {0} has the account locked. No operations are allowed.")]
+    LockedAccount(u16),
+    #[error("The client number: {0} does not have associated the transaction with number: {1}")]
+    TransactionDoesNotExist(u16, u32),
+    #[error("The client number: {0} does not have enough credit for the requested withdrawal.")]
+    NotEnoughCredit(u16),
+    #[error("The transaction number: {0} for client number: {1} is not in a state that allows this operation.")]
+    InvalidDisputeState(u32, u16),
+    #[error("The client number: {0} would end up with a negative held balance from this operation.")]
+    NegativeHeldAmount(u16),
+    #[error("The client number: {0} submitted a deposit or withdrawal with a negative amount.")]
+    NegativeAmount(u16),
+}
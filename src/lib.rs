@@ -0,0 +1,10 @@
+pub mod client;
+pub mod engine;
+mod error;
+pub mod report;
+pub mod token;
+pub mod transaction;
+
+pub use engine::Engine;
+pub use error::Error;
+pub use transaction::{Transaction, TxKind, TxState};
@@ -1,133 +1,268 @@
 use csv::{self, Trim};
-use serde::Deserialize;
+use rayon::prelude::*;
 use std::collections::BTreeMap;
 use std::env;
+use std::io::{self, Read};
 use std::process::exit;
 use std::result::Result;
-use thiserror::Error;
 
-use rust_decimal::prelude::*;
+use toy_pay::client::Client;
+use toy_pay::engine::apply_token;
+use toy_pay::report::Report;
+use toy_pay::token::Token;
 
-mod transaction;
-use transaction::Transaction;
-
-mod client;
-use client::Client;
-
-#[derive(Debug, Deserialize, Clone, Copy)]
-#[serde(rename_all = "lowercase")]
-enum TransactionType {
-    Deposit,
-    Withdrawal,
-    Dispute,
-    Resolve,
-    Chargeback,
-}
-
-#[derive(Debug, Deserialize, Clone, Copy)]
-struct Token {
-    #[serde(rename = "type")]
-    transaction_type: TransactionType,
-    #[serde(rename = "client")]
-    client_id: u16,
-    #[serde(rename = "tx")]
-    transaction_id: u32,
-    amount: Option<Decimal>,
-}
-
-/// Reads data from a file into a reader and deserializes each record
+/// Opens a csv `Reader` over either a file path or, when `path` is `None`, stdin.
+///
+/// Uses a flexible reader since dispute-family rows omit the trailing
+/// `amount` column entirely.
 ///
 /// # Error
 ///
 /// If an error occurs, the error is returned to `main`.
-fn read_from_file(path: &str) -> Result<Vec<Token>, csv::Error> {
-    // Creates a new csv `Reader` from a file
-    let mut reader = csv::ReaderBuilder::new().trim(Trim::All).from_path(path)?;
+fn reader_from(path: Option<&str>) -> Result<csv::Reader<Box<dyn Read>>, csv::Error> {
+    let source: Box<dyn Read> = match path {
+        Some(path) => Box::new(std::fs::File::open(path)?),
+        None => Box::new(io::stdin()),
+    };
+
+    let mut reader = csv::ReaderBuilder::new()
+        .trim(Trim::All)
+        .flexible(true)
+        .from_reader(source);
 
     // Retrieve and print header record
     let _headers = reader.headers()?;
 
-    Ok(reader.deserialize().flatten().collect())
-}
-
-#[derive(Debug, PartialEq, Error)]
-pub enum Error {
-    /// There client that requested an operation has the account locked.
-    #[error("The client number: {0} has the account locked. No operations are allowed.")]
-    LockedAccount(u16),
-    #[error("The client number: {0} does not have associated the transaction with number: {1}")]
-    TransactionDoesNotExist(u16, u32),
-    #[error("The client number: {0} does not have enough credit for the requested withdrawal.")]
-    NotEnoughCredit(u16),
-    #[error("The transaction number: {0} for client number: {1} is not disputed.")]
-    TransactionNotDisputed(u32, u16),
+    Ok(reader)
 }
 
+/// Processes the token stream sequentially into a single `BTreeMap`,
+/// applying each record in file order. This is the default, deterministic
+/// path.
+///
+/// Every record that is rejected by `apply_token` is recorded in the
+/// returned `Report` rather than discarded, keyed by its 1-based position
+/// among the data rows.
 fn process_requests(
-    tokens: Vec<Token>,
+    tokens: impl Iterator<Item = Result<Token, csv::Error>>,
     mut clients: BTreeMap<u16, Client>,
-) -> BTreeMap<u16, Client> {
-    for token in tokens {
-        match token.transaction_type {
-            TransactionType::Deposit => {
-                if let Some(amount) = token.amount {
-                    let client = clients
-                        .entry(token.client_id)
-                        .or_insert_with(|| Client::new(token.client_id));
-                    if client.deposit(token.transaction_id, amount).is_err() {
-                        // do some error handling here
-                    }
-                }
+) -> (BTreeMap<u16, Client>, Report) {
+    let mut report = Report::default();
+    for (index, token) in tokens.enumerate() {
+        let record = index + 1;
+        let token = match token {
+            Ok(token) => token,
+            Err(error) => {
+                report.push_parse_error(record, error);
+                continue;
             }
-            TransactionType::Withdrawal => {
-                if let Some(amount) = token.amount {
-                    let client = clients
-                        .entry(token.client_id)
-                        .or_insert_with(|| Client::new(token.client_id));
-                    if client.withdrawal(token.transaction_id, amount).is_err() {
-                        // do some error handling here
-                    }
-                }
-            }
-            TransactionType::Dispute => {
-                let client = clients
-                    .entry(token.client_id)
-                    .or_insert_with(|| Client::new(token.client_id));
-                if client.dispute(token.transaction_id).is_err() {
-                    // do some error handling here
-                }
-            }
-            TransactionType::Resolve => {
-                let client = clients
-                    .entry(token.client_id)
-                    .or_insert_with(|| Client::new(token.client_id));
-                if client.resolve(token.transaction_id).is_err() {
-                    // do some error handling here
-                }
-            }
-            TransactionType::Chargeback => {
-                let client = clients
-                    .entry(token.client_id)
-                    .or_insert_with(|| Client::new(token.client_id));
-                if client.chargeback(token.transaction_id).is_err() {
-                    // do some error handling here
+        };
+        let client_id = token.client_id();
+        let transaction_id = token.transaction_id();
+        let client = clients
+            .entry(client_id)
+            .or_insert_with(|| Client::new(client_id));
+        if let Err(error) = apply_token(client, token) {
+            report.push(record, client_id, transaction_id, error);
+        }
+    }
+    (clients, report)
+}
+
+/// Processes the token stream by sharding records per `client_id` and
+/// running each client's shard on a separate worker. Ordering is preserved
+/// within a client's own shard, but is only relaxed across clients, since
+/// each client's balance and disputes are independent of every other
+/// client's.
+///
+/// Each shard accumulates its own `Report`; the reports are merged and
+/// re-sorted by record so the result reads as if it had been produced
+/// sequentially.
+fn process_requests_parallel(
+    tokens: impl Iterator<Item = Result<Token, csv::Error>>,
+) -> (BTreeMap<u16, Client>, Report) {
+    let mut shards: BTreeMap<u16, Vec<(usize, Token)>> = BTreeMap::new();
+    let mut report = Report::default();
+    for (index, token) in tokens.enumerate() {
+        let record = index + 1;
+        match token {
+            Ok(token) => shards.entry(token.client_id()).or_default().push((record, token)),
+            Err(error) => report.push_parse_error(record, error),
+        }
+    }
+
+    let (clients, reports): (BTreeMap<_, _>, Vec<Report>) = shards
+        .into_par_iter()
+        .map(|(client_id, tokens)| {
+            let mut client = Client::new(client_id);
+            let mut report = Report::default();
+            for (record, token) in tokens {
+                let transaction_id = token.transaction_id();
+                if let Err(error) = apply_token(&mut client, token) {
+                    report.push(record, client_id, transaction_id, error);
                 }
             }
-        }
+            ((client_id, client), report)
+        })
+        .unzip();
+
+    for shard_report in reports {
+        report.extend(shard_report);
     }
-    clients
+    report.sort_by_record();
+
+    (clients, report)
 }
 
-fn main() {
-    let args: Vec<String> = env::args().collect();
-    if args.len() < 2 {
-        exit(1);
+/// Writes each client's rounded balance to stdout as CSV, ordered by client id.
+fn write_balances(clients: &BTreeMap<u16, Client>) -> Result<(), csv::Error> {
+    let mut writer = csv::Writer::from_writer(io::stdout());
+    for client in clients.values() {
+        writer.serialize(client.balance())?;
     }
-    let tokens_res = read_from_file(&args[1]);
-    if let Ok(tokens) = tokens_res {
-        let mut clients = BTreeMap::<u16, Client>::new();
-        clients = process_requests(tokens, clients);
+    writer.flush()?;
+    Ok(())
+}
+
+/// Writes every rejected record to stderr as a line of JSON, so a run with
+/// failures is auditable rather than opaquely lossy.
+fn write_report(report: &Report) -> Result<(), serde_json::Error> {
+    for rejected in &report.rejected {
+        serde_json::to_writer(io::stderr(), rejected)?;
+        eprintln!();
+    }
+    Ok(())
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let parallel = args.iter().any(|arg| arg == "--parallel");
+    let path = args.iter().find(|arg| *arg != "--parallel").map(String::as_str);
+
+    let reader_res = reader_from(path);
+    if let Ok(mut reader) = reader_res {
+        let (clients, report) = if parallel {
+            process_requests_parallel(reader.deserialize())
+        } else {
+            process_requests(reader.deserialize(), BTreeMap::new())
+        };
+        if write_balances(&clients).is_err() {
+            exit(1);
+        }
+        if !report.is_empty() {
+            let _ = write_report(&report);
+            exit(2);
+        }
     } else {
         exit(1);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    fn sample_tokens() -> Vec<Result<Token, csv::Error>> {
+        vec![
+            Ok(Token::Deposit {
+                client_id: 1,
+                transaction_id: 1,
+                amount: dec!(5.0000),
+            }),
+            Ok(Token::Deposit {
+                client_id: 2,
+                transaction_id: 2,
+                amount: dec!(3.0000),
+            }),
+            Ok(Token::Withdrawal {
+                client_id: 1,
+                transaction_id: 3,
+                amount: dec!(1.0000),
+            }),
+            Ok(Token::Dispute {
+                client_id: 1,
+                transaction_id: 1,
+            }),
+            Ok(Token::Resolve {
+                client_id: 1,
+                transaction_id: 1,
+            }),
+            Ok(Token::Deposit {
+                client_id: 2,
+                transaction_id: 4,
+                amount: dec!(2.0000),
+            }),
+            Ok(Token::Dispute {
+                client_id: 2,
+                transaction_id: 4,
+            }),
+            Ok(Token::Chargeback {
+                client_id: 2,
+                transaction_id: 4,
+            }),
+        ]
+    }
+
+    #[test]
+    fn test_parallel_matches_sequential() {
+        let (sequential, sequential_report) =
+            process_requests(sample_tokens().into_iter(), BTreeMap::new());
+        let (parallel, parallel_report) = process_requests_parallel(sample_tokens().into_iter());
+
+        let sequential_balances: Vec<_> = sequential.values().map(Client::balance).collect();
+        let parallel_balances: Vec<_> = parallel.values().map(Client::balance).collect();
+        assert_eq!(sequential_balances, parallel_balances);
+        assert_eq!(sequential_report, parallel_report);
+    }
+
+    #[test]
+    fn test_rejected_record_is_reported_with_its_position() {
+        let tokens = vec![
+            Ok(Token::Withdrawal {
+                client_id: 1,
+                transaction_id: 1,
+                amount: dec!(5.0000),
+            }),
+            Ok(Token::Deposit {
+                client_id: 1,
+                transaction_id: 2,
+                amount: dec!(5.0000),
+            }),
+        ];
+
+        let (_, report) = process_requests(tokens.into_iter(), BTreeMap::new());
+
+        assert_eq!(report.rejected.len(), 1);
+        assert_eq!(report.rejected[0].record, 1);
+        assert_eq!(report.rejected[0].client_id, Some(1));
+        assert_eq!(report.rejected[0].transaction_id, Some(1));
+    }
+
+    #[test]
+    fn test_malformed_record_is_reported_without_shifting_later_indices() {
+        let csv = "type,client,tx,amount\n\
+                   deposit,1,1,\n\
+                   deposit,2,2,-3.0\n\
+                   deposit,3,3,not_a_number\n\
+                   deposit,4,4,5.0\n\
+                   withdrawal,4,5,1.0\n";
+        let mut reader = csv::ReaderBuilder::new()
+            .trim(csv::Trim::All)
+            .flexible(true)
+            .from_reader(csv.as_bytes());
+        reader.headers().unwrap();
+
+        let (clients, report) = process_requests(reader.deserialize(), BTreeMap::new());
+
+        assert_eq!(clients.len(), 1);
+        assert_eq!(clients[&4].balance().available, dec!(4.0000));
+
+        let bad_records: Vec<usize> = report.rejected.iter().map(|r| r.record).collect();
+        assert_eq!(bad_records, vec![1, 2, 3]);
+        for rejected in &report.rejected {
+            assert_eq!(rejected.client_id, None);
+            assert_eq!(rejected.transaction_id, None);
+        }
+    }
+}
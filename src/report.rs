@@ -0,0 +1,63 @@
+use crate::Error;
+use serde::Serialize;
+
+/// A single processing failure, keyed to the input record that caused it.
+///
+/// `record` is the 1-based position of the record among the data rows (the
+/// header row is not counted), so record 1 is the first row after the
+/// header.
+///
+/// `client_id`/`transaction_id` are `None` for records that failed to parse
+/// in the first place, since a malformed row never yields a `Token` to read
+/// those fields from.
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct RejectedRecord {
+    pub record: usize,
+    pub client_id: Option<u16>,
+    pub transaction_id: Option<u32>,
+    pub error: String,
+}
+
+/// An audit trail of every operation `process_requests`/`process_requests_parallel`
+/// rejected, so a batch run is auditable instead of silently lossy.
+#[derive(Debug, Default, Serialize, Clone, PartialEq)]
+pub struct Report {
+    pub rejected: Vec<RejectedRecord>,
+}
+
+impl Report {
+    pub fn push(&mut self, record: usize, client_id: u16, transaction_id: u32, error: Error) {
+        self.rejected.push(RejectedRecord {
+            record,
+            client_id: Some(client_id),
+            transaction_id: Some(transaction_id),
+            error: error.to_string(),
+        });
+    }
+
+    /// Records a record that never made it to a `Token`, e.g. a CSV row that
+    /// failed to deserialize. The client and transaction it referred to are
+    /// unknown, so only its position and the underlying error are kept.
+    pub fn push_parse_error(&mut self, record: usize, error: csv::Error) {
+        self.rejected.push(RejectedRecord {
+            record,
+            client_id: None,
+            transaction_id: None,
+            error: error.to_string(),
+        });
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rejected.is_empty()
+    }
+
+    pub fn extend(&mut self, other: Report) {
+        self.rejected.extend(other.rejected);
+    }
+
+    /// Restores chronological order by input record, needed after merging
+    /// reports gathered from per-client shards processed out of order.
+    pub fn sort_by_record(&mut self) {
+        self.rejected.sort_by_key(|rejected| rejected.record);
+    }
+}
@@ -0,0 +1,230 @@
+use rust_decimal::prelude::*;
+use serde::Deserialize;
+use thiserror::Error;
+
+#[derive(Debug, Deserialize, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+enum TransactionType {
+    Deposit,
+    Withdrawal,
+    Dispute,
+    Resolve,
+    Chargeback,
+}
+
+/// The shape of a CSV record before it has been validated into a `Token`.
+#[derive(Debug, Deserialize, Clone, Copy)]
+struct RawToken {
+    #[serde(rename = "type")]
+    transaction_type: TransactionType,
+    #[serde(rename = "client")]
+    client_id: u16,
+    #[serde(rename = "tx")]
+    transaction_id: u32,
+    amount: Option<Decimal>,
+}
+
+#[derive(Debug, PartialEq, Error)]
+pub enum ParseError {
+    #[error("The transaction number: {1} for client number: {0} is missing its required amount.")]
+    MissingAmount(u16, u32),
+    #[error("The transaction number: {1} for client number: {0} has a negative amount.")]
+    NegativeAmount(u16, u32),
+}
+
+/// A validated, typed CSV record. Deposit/withdrawal rows are guaranteed to
+/// carry a non-negative amount; dispute-family rows carry none.
+#[derive(Debug, Deserialize, Clone, Copy)]
+#[serde(try_from = "RawToken")]
+pub enum Token {
+    Deposit {
+        client_id: u16,
+        transaction_id: u32,
+        amount: Decimal,
+    },
+    Withdrawal {
+        client_id: u16,
+        transaction_id: u32,
+        amount: Decimal,
+    },
+    Dispute {
+        client_id: u16,
+        transaction_id: u32,
+    },
+    Resolve {
+        client_id: u16,
+        transaction_id: u32,
+    },
+    Chargeback {
+        client_id: u16,
+        transaction_id: u32,
+    },
+}
+
+impl Token {
+    /// The client this record applies to, regardless of variant.
+    pub fn client_id(&self) -> u16 {
+        match self {
+            Token::Deposit { client_id, .. }
+            | Token::Withdrawal { client_id, .. }
+            | Token::Dispute { client_id, .. }
+            | Token::Resolve { client_id, .. }
+            | Token::Chargeback { client_id, .. } => *client_id,
+        }
+    }
+
+    /// The transaction this record applies to, regardless of variant.
+    pub fn transaction_id(&self) -> u32 {
+        match self {
+            Token::Deposit { transaction_id, .. }
+            | Token::Withdrawal { transaction_id, .. }
+            | Token::Dispute { transaction_id, .. }
+            | Token::Resolve { transaction_id, .. }
+            | Token::Chargeback { transaction_id, .. } => *transaction_id,
+        }
+    }
+}
+
+impl TryFrom<RawToken> for Token {
+    type Error = ParseError;
+
+    fn try_from(raw: RawToken) -> Result<Self, Self::Error> {
+        match raw.transaction_type {
+            TransactionType::Deposit => Ok(Token::Deposit {
+                client_id: raw.client_id,
+                transaction_id: raw.transaction_id,
+                amount: require_amount(raw)?,
+            }),
+            TransactionType::Withdrawal => Ok(Token::Withdrawal {
+                client_id: raw.client_id,
+                transaction_id: raw.transaction_id,
+                amount: require_amount(raw)?,
+            }),
+            TransactionType::Dispute => Ok(Token::Dispute {
+                client_id: raw.client_id,
+                transaction_id: raw.transaction_id,
+            }),
+            TransactionType::Resolve => Ok(Token::Resolve {
+                client_id: raw.client_id,
+                transaction_id: raw.transaction_id,
+            }),
+            TransactionType::Chargeback => Ok(Token::Chargeback {
+                client_id: raw.client_id,
+                transaction_id: raw.transaction_id,
+            }),
+        }
+    }
+}
+
+fn require_amount(raw: RawToken) -> Result<Decimal, ParseError> {
+    let amount = raw
+        .amount
+        .ok_or(ParseError::MissingAmount(raw.client_id, raw.transaction_id))?;
+    if amount < Decimal::ZERO {
+        return Err(ParseError::NegativeAmount(raw.client_id, raw.transaction_id));
+    }
+    Ok(amount)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    fn raw(transaction_type: TransactionType, amount: Option<Decimal>) -> RawToken {
+        RawToken {
+            transaction_type,
+            client_id: 1,
+            transaction_id: 2,
+            amount,
+        }
+    }
+
+    #[test]
+    fn test_deposit_requires_amount() {
+        let error = Token::try_from(raw(TransactionType::Deposit, None)).unwrap_err();
+        assert_eq!(error, ParseError::MissingAmount(1, 2));
+    }
+
+    #[test]
+    fn test_deposit_rejects_negative_amount() {
+        let error = Token::try_from(raw(TransactionType::Deposit, Some(dec!(-1.0)))).unwrap_err();
+        assert_eq!(error, ParseError::NegativeAmount(1, 2));
+    }
+
+    #[test]
+    fn test_deposit_ok_case() {
+        let token = Token::try_from(raw(TransactionType::Deposit, Some(dec!(5.0)))).unwrap();
+        match token {
+            Token::Deposit {
+                client_id,
+                transaction_id,
+                amount,
+            } => {
+                assert_eq!(client_id, 1);
+                assert_eq!(transaction_id, 2);
+                assert_eq!(amount, dec!(5.0));
+            }
+            other => panic!("expected Token::Deposit, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_withdrawal_requires_amount() {
+        let error = Token::try_from(raw(TransactionType::Withdrawal, None)).unwrap_err();
+        assert_eq!(error, ParseError::MissingAmount(1, 2));
+    }
+
+    #[test]
+    fn test_withdrawal_rejects_negative_amount() {
+        let error =
+            Token::try_from(raw(TransactionType::Withdrawal, Some(dec!(-1.0)))).unwrap_err();
+        assert_eq!(error, ParseError::NegativeAmount(1, 2));
+    }
+
+    #[test]
+    fn test_withdrawal_ok_case() {
+        let token = Token::try_from(raw(TransactionType::Withdrawal, Some(dec!(5.0)))).unwrap();
+        match token {
+            Token::Withdrawal {
+                client_id,
+                transaction_id,
+                amount,
+            } => {
+                assert_eq!(client_id, 1);
+                assert_eq!(transaction_id, 2);
+                assert_eq!(amount, dec!(5.0));
+            }
+            other => panic!("expected Token::Withdrawal, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_dispute_ok_case() {
+        let token = Token::try_from(raw(TransactionType::Dispute, None)).unwrap();
+        assert_eq!(token.client_id(), 1);
+        assert_eq!(token.transaction_id(), 2);
+        assert!(matches!(token, Token::Dispute { .. }));
+    }
+
+    #[test]
+    fn test_resolve_ok_case() {
+        let token = Token::try_from(raw(TransactionType::Resolve, None)).unwrap();
+        assert!(matches!(token, Token::Resolve { .. }));
+    }
+
+    #[test]
+    fn test_chargeback_ok_case() {
+        let token = Token::try_from(raw(TransactionType::Chargeback, None)).unwrap();
+        assert!(matches!(token, Token::Chargeback { .. }));
+    }
+
+    #[test]
+    fn test_dispute_family_ignores_amount() {
+        // Dispute-family rows carry no amount column, but a flexible CSV
+        // reader may still hand back a stray value; it must not affect
+        // validation since these variants never read `amount`.
+        let token = Token::try_from(raw(TransactionType::Dispute, Some(dec!(-1.0)))).unwrap();
+        assert!(matches!(token, Token::Dispute { .. }));
+    }
+}
@@ -1,19 +1,44 @@
 use rust_decimal::prelude::*;
 use serde::Deserialize;
 
+/// The lifecycle state of a transaction with respect to the dispute process.
+///
+/// A transaction starts out `Processed` and can only move forward along the
+/// legal edges of this graph: `Processed -> Disputed -> {Resolved, ChargedBack}`.
+/// `Resolved` and `ChargedBack` are terminal.
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum TxState {
+    Processed,
+    Disputed,
+    Resolved,
+    ChargedBack,
+}
+
+/// Whether a transaction added funds to the client's account or removed them.
+///
+/// Disputing a deposit and disputing a withdrawal pull funds in opposite
+/// directions, so `Client` needs this to apply the correct sign.
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum TxKind {
+    Deposit,
+    Withdrawal,
+}
+
 #[derive(Debug, Deserialize, Clone, Copy)]
 pub struct Transaction {
     _transaction_id: u32,
     amount: Decimal,
-    disputed: bool,
+    kind: TxKind,
+    state: TxState,
 }
 
 impl Transaction {
-    pub fn new(id: u32, amount: Decimal) -> Self {
+    pub fn new(id: u32, amount: Decimal, kind: TxKind) -> Self {
         Transaction {
             _transaction_id: id,
             amount,
-            disputed: false,
+            kind,
+            state: TxState::Processed,
         }
     }
 
@@ -21,11 +46,15 @@ impl Transaction {
         self.amount
     }
 
-    pub fn get_dispute_status(self) -> bool {
-        self.disputed
+    pub fn get_kind(self) -> TxKind {
+        self.kind
+    }
+
+    pub fn get_state(self) -> TxState {
+        self.state
     }
 
-    pub fn set_dispute_status(&mut self, dispute: bool) {
-        self.disputed = dispute;
+    pub fn set_state(&mut self, state: TxState) {
+        self.state = state;
     }
 }